@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use sha1::{Digest, Sha1};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::global_path;
+
+// A zip whose entries overwrite `.class` files inside the client jar in place, applied in
+// instance order before the game is launched.
+#[derive(Debug, Clone)]
+pub struct JarMod {
+    pub name: String,
+    pub path: PathBuf,
+    pub sha1: String
+}
+
+// Builds `bin/minecraft.jar` by writing each jarmod's entries first (last one in `jarmods` wins)
+// then filling in everything the jarmods didn't touch from the base client jar, so the result
+// has exactly one copy of each zip entry. A jarmod whose sha1 doesn't verify is dropped rather
+// than aborting the whole patch, since the rest of the stack can still be usable without it.
+pub fn build_patched_client_jar(instance_dir: &Path, client_jar_path: &Path, jarmods: &Vec<JarMod>) -> io::Result<PathBuf> {
+
+    let output_path = global_path::combine_common_paths_absolute(instance_dir, Path::new("bin/minecraft.jar"));
+
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut writer = ZipWriter::new(File::create(&output_path)?);
+    let mut written_entries = std::collections::HashSet::new();
+
+    // Later jarmods win, so apply them before the base jar and skip any entry name the base
+    // jar would otherwise clobber.
+    for jarmod in jarmods.iter().rev() {
+
+        if !verify_sha1(jarmod) {
+            continue;
+        }
+
+        let mut archive = ZipArchive::new(File::open(&jarmod.path)?)?;
+
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index)?;
+
+            if !written_entries.insert(entry.name().to_string()) {
+                continue;
+            }
+
+            writer.start_file(entry.name(), FileOptions::default())?;
+            let mut buffer = Vec::new();
+            entry.read_to_end(&mut buffer)?;
+            io::Write::write_all(&mut writer, &buffer)?;
+        }
+    }
+
+    let mut base_archive = ZipArchive::new(File::open(client_jar_path)?)?;
+
+    for index in 0..base_archive.len() {
+        let mut entry = base_archive.by_index(index)?;
+
+        if written_entries.contains(entry.name()) {
+            continue;
+        }
+
+        writer.start_file(entry.name(), FileOptions::default())?;
+        let mut buffer = Vec::new();
+        entry.read_to_end(&mut buffer)?;
+        io::Write::write_all(&mut writer, &buffer)?;
+    }
+
+    writer.finish()?;
+
+    Ok(output_path)
+}
+
+fn verify_sha1(jarmod: &JarMod) -> bool {
+
+    let Ok(mut file) = File::open(&jarmod.path) else { return false; };
+    let mut buffer = Vec::new();
+
+    if file.read_to_end(&mut buffer).is_err() {
+        return false;
+    }
+
+    let digest = Sha1::digest(&buffer);
+    format!("{:x}", digest) == jarmod.sha1.to_lowercase()
+}
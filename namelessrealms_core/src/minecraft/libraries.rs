@@ -1,4 +1,7 @@
+use std::collections::HashMap;
 use std::path::{PathBuf, Path};
+use regex::Regex;
+use tracing::warn;
 use crate::{utils, global_path, version_metadata::LibrariesFile};
 use super::version_metadata::{Libraries, LibrariesRules};
 
@@ -19,14 +22,14 @@ pub struct LibrariesJar {
     pub download_url: String
 }
 
-pub fn is_libraries(libraries: &Vec<Libraries>) -> Vec<LibrariesJar> {
+pub fn is_libraries(libraries: &Vec<Libraries>, features: &HashMap<String, bool>) -> Vec<LibrariesJar> {
 
     let mut allow_libs: Vec<LibrariesJar> = Vec::new();
 
     for lib in libraries.iter() {
 
         if let Some(rules) = &lib.rules {
-            if !is_rules(rules) { continue; }
+            if !is_rules(rules, features) { continue; }
         }
 
         add_allow_libs(lib, &mut allow_libs);
@@ -103,7 +106,148 @@ fn add_allow_libs(item: &Libraries, allow_libs: &mut Vec<LibrariesJar>) {
     }
 }
 
-pub fn is_rules(rules: &Vec<LibrariesRules>) -> bool {
+// Versions we've confirmed publish `natives-linux-arm64`/`natives-macos-arm64` classifiers
+// on Maven Central next to the x86/x64 natives Mojang's manifest references. Anything else is
+// logged rather than silently left on the wrong native.
+const LWJGL_ARM_NATIVE_VERSIONS: &[&str] = &["3.2.2", "3.2.3", "3.3.1", "3.3.2", "3.3.3"];
+
+const MAVEN_CENTRAL_BASE_URL: &str = "https://repo1.maven.org/maven2";
+
+// A launch shouldn't hang indefinitely on a flaky Maven Central; fail fast and keep the x86/x64
+// native instead.
+const LWJGL_ARM_FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct LwjglArmNativeCacheEntry {
+    sha1: String,
+    size: u32
+}
+
+// Resolved arm64 native checksums, keyed by `group:artifact:version:classifier`, persisted next
+// to the other per-install state so a launch only has to hit the network for a given LWJGL
+// artifact once instead of on every game start.
+fn lwjgl_arm_cache_path() -> PathBuf {
+    global_path::get_common_dir_path().join("lwjgl_arm_native_cache.json")
+}
+
+fn load_lwjgl_arm_cache() -> HashMap<String, LwjglArmNativeCacheEntry> {
+    std::fs::read_to_string(lwjgl_arm_cache_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_lwjgl_arm_cache(cache: &HashMap<String, LwjglArmNativeCacheEntry>) {
+    if let Ok(contents) = serde_json::to_string(cache) {
+        let _ = std::fs::write(lwjgl_arm_cache_path(), contents);
+    }
+}
+
+// Parses the Maven-style `org/lwjgl/<artifact>/<version>/...` relative path Mojang's manifest
+// uses, returning `(artifact, version)` when the library belongs to the `org.lwjgl` group.
+fn lwjgl_coordinate(relative_path: &Path) -> Option<(String, String)> {
+
+    let components: Vec<&str> = relative_path.to_str()?.split('/').collect();
+
+    if components.len() < 4 || components[0] != "org" || components[1] != "lwjgl" {
+        return None;
+    }
+
+    Some((components[2].to_string(), components[3].to_string()))
+}
+
+// Maven Central publishes a `<file>.sha1` sidecar next to every artifact, including the arm64
+// native classifiers; that's the one source of truth that can't go stale like a bundled table
+// would. A `HEAD` on the jar itself gives us its real size.
+fn fetch_lwjgl_arm_native(download_url: &str) -> Option<LwjglArmNativeCacheEntry> {
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(LWJGL_ARM_FETCH_TIMEOUT)
+        .build()
+        .ok()?;
+
+    let sha1 = client.get(format!("{}.sha1", download_url)).send().ok()?
+        .error_for_status().ok()?
+        .text().ok()?
+        .split_whitespace().next()?
+        .to_lowercase();
+
+    let size = client.head(download_url).send().ok()?
+        .error_for_status().ok()?
+        .content_length()? as u32;
+
+    Some(LwjglArmNativeCacheEntry { sha1, size })
+}
+
+pub fn apply_lwjgl_arm_overrides(libs: &mut Vec<LibrariesJar>) {
+
+    if utils::get_os_arch() != utils::OSArch::Aarch64 {
+        return;
+    }
+
+    let native_classifier = match utils::get_os_type() {
+        utils::OSType::Linux => "natives-linux-arm64",
+        utils::OSType::MacOS => "natives-macos-arm64",
+        // No ARM64 Windows LWJGL natives are published; leave the resolved x86/x64 library as-is.
+        utils::OSType::Windows => return
+    };
+
+    let mut cache = load_lwjgl_arm_cache();
+    let mut cache_dirty = false;
+
+    for lib in libs.iter_mut() {
+
+        // The platform-independent artifact jar and the natives jar share the same Maven
+        // directory (`org/lwjgl/<artifact>/<version>/`) and only differ by filename, so this
+        // must gate on the resolved entry already being a natives classifier - otherwise we'd
+        // also rewrite the jar holding LWJGL's actual classes and break every LWJGL call.
+        if lib.r#type != LibrariesJarType::Natives {
+            continue;
+        }
+
+        let Some((artifact, version)) = lwjgl_coordinate(&lib.relative_path) else { continue; };
+
+        if !LWJGL_ARM_NATIVE_VERSIONS.contains(&version.as_str()) {
+            warn!("no known arm64 native for org.lwjgl:{}:{}, leaving x86/x64 native in place", artifact, version);
+            continue;
+        }
+
+        let relative_path = format!("org/lwjgl/{artifact}/{version}/{artifact}-{version}-{native_classifier}.jar");
+        let download_url = format!("{}/{}", MAVEN_CENTRAL_BASE_URL, relative_path);
+        let cache_key = format!("org.lwjgl:{artifact}:{version}:{native_classifier}");
+
+        let cache_entry = match cache.get(&cache_key) {
+            Some(entry) => entry.clone(),
+            None => {
+                let Some(entry) = fetch_lwjgl_arm_native(&download_url) else {
+                    warn!("failed to resolve arm64 native for org.lwjgl:{}:{}, leaving x86/x64 native in place", artifact, version);
+                    continue;
+                };
+                cache.insert(cache_key, entry.clone());
+                cache_dirty = true;
+                entry
+            }
+        };
+
+        let relative_path = Path::new(&relative_path).to_path_buf();
+
+        lib.name = relative_path.file_name().unwrap().to_string_lossy().to_string();
+        lib.path = global_path::combine_common_paths_absolute(Path::new("libraries"), &relative_path);
+        lib.download_url = download_url;
+        lib.relative_path = relative_path;
+        lib.sha1 = cache_entry.sha1;
+        lib.size = cache_entry.size;
+    }
+
+    if cache_dirty {
+        save_lwjgl_arm_cache(&cache);
+    }
+}
+
+// Mojang's rule semantics are cumulative rather than first-match: start from an implicit
+// `disallow`, walk every rule in order, and let each rule whose conditions all match overwrite
+// the running action. A rule with no `os`/`features` conditions always matches.
+pub fn is_rules(rules: &Vec<LibrariesRules>, features: &HashMap<String, bool>) -> bool {
 
     let os_type = || {
         match utils::get_os_type() {
@@ -121,28 +265,43 @@ pub fn is_rules(rules: &Vec<LibrariesRules>) -> bool {
         }.to_string()
     };
 
-    for rule in rules.iter() {
-        
-        if rule.action == "allow" {
-            if let Some(os) = &rule.os {
-
-                if let Some(os_name) = os.name.as_ref() {
-                    return os_type() == os_name.to_string();
-                } else if let Some(os_arch_name) = os.arch.as_ref() {
-                    return os_arch() == os_arch_name.to_string()
-                }
+    let rule_matches = |rule: &LibrariesRules| -> bool {
+
+        if let Some(os) = &rule.os {
+
+            if let Some(os_name) = os.name.as_ref() {
+                if os_type() != os_name.to_string() { return false; }
+            }
+
+            if let Some(os_arch_name) = os.arch.as_ref() {
+                if os_arch() != os_arch_name.to_string() { return false; }
+            }
 
+            if let Some(os_version) = os.version.as_ref() {
+                let version_matches = Regex::new(os_version)
+                    .map(|regex| regex.is_match(&utils::get_os_version()))
+                    .unwrap_or(false);
+                if !version_matches { return false; }
             }
         }
 
-        if rule.action == "disallow" {
-            if let Some(os) = &rule.os {
-                if let Some(os_name) = os.name.as_ref() {
-                    return os_type() != os_name.to_string();
-                }
+        if let Some(rule_features) = &rule.features {
+            for (feature_name, required_value) in rule_features.iter() {
+                let active_value = features.get(feature_name).copied().unwrap_or(false);
+                if active_value != *required_value { return false; }
             }
         }
+
+        true
+    };
+
+    let mut action = "disallow";
+
+    for rule in rules.iter() {
+        if rule_matches(rule) {
+            action = rule.action.as_str();
+        }
     }
 
-    true
+    action == "allow"
 }
\ No newline at end of file
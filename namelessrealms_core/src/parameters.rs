@@ -1,9 +1,20 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::{utils::{self, OSType, OSArch}, version_metadata::VersionMetadata, global_path};
+use crate::{utils::{self, OSType, OSArch}, version_metadata::VersionMetadata, global_path, minecraft::{libraries, jarmod::JarMod, jarmod}};
+
+// `version_metadata` has no `mainClass`/`minecraftArguments` data for pre-1.6 versions, so we
+// can't point the JVM straight at Mojang's entry point the way `build_113above`/`build_112later`
+// do; `LEGACY_LAUNCHER_MAIN_CLASS` stands in for it and is responsible for constructing the
+// real applet named by `LEGACY_DEFAULT_APPLET_CLASS` itself.
+const LEGACY_LAUNCHER_MAIN_CLASS: &str = "com.mckismetlab.launcher.applet.AppletWrapper";
+const LEGACY_DEFAULT_APPLET_CLASS: &str = "net.minecraft.client.MinecraftApplet";
+// Ships inside the launcher's own distribution (not part of any `version_metadata` library
+// list), so it has to be added to the classpath explicitly for legacy launches.
+const LEGACY_LAUNCHER_WRAPPER_JAR_NAME: &str = "mckismetlab-launcher-wrapper.jar";
 
 #[derive(Debug)]
 pub struct JavaStartParameters {
@@ -11,18 +22,48 @@ pub struct JavaStartParameters {
     pub parameters: Vec<String>
 }
 
+// Everything that's specific to *who* is launching and *how much* RAM they get, as opposed to
+// `VersionMetadata` which is specific to *what* is being launched. Replaces the previous
+// hardcoded demo account and fixed heap size so the builder can launch arbitrary accounts.
+#[derive(Debug, Clone)]
+pub struct LaunchProfile {
+    pub player_name: String,
+    pub player_uuid: String,
+    pub access_token: String,
+    pub user_type: String,
+    pub version_type: String,
+    pub xuid: String,
+    pub client_id: String,
+    pub min_heap_mb: u32,
+    pub max_heap_mb: u32,
+    pub extra_jvm_arguments: Vec<String>,
+    pub game_directory: PathBuf,
+    // Overrides `LEGACY_DEFAULT_APPLET_CLASS` for instances using a non-vanilla applet (e.g. an
+    // old Forge/Risugami loader); `None` keeps the vanilla applet.
+    pub applet_class: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct BuildParameters<'a> {
     version_metadata: &'a VersionMetadata,
     natives_dir_path: PathBuf,
+    // Active feature flags (e.g. `is_demo_user`, `has_custom_resolution`) forwarded to the
+    // libraries rule engine; game arguments are not filtered by them.
+    features: HashMap<String, bool>,
+    // Pre-1.6-style jarmods to layer over the client jar before launch, in apply order.
+    jarmods: Vec<JarMod>,
+    launch_profile: LaunchProfile,
 }
 
 impl BuildParameters<'_> {
 
-    pub fn new(version_metadata: &VersionMetadata) -> BuildParameters {
+    pub fn new<'a>(version_metadata: &'a VersionMetadata, features: HashMap<String, bool>, jarmods: Vec<JarMod>, launch_profile: LaunchProfile) -> BuildParameters<'a> {
         BuildParameters {
             version_metadata: version_metadata,
             natives_dir_path: global_path::get_common_dir_path().join("bin").join(Uuid::new_v4().to_string().split("-").next().unwrap()),
+            features,
+            jarmods,
+            launch_profile,
         }
     }
 
@@ -31,8 +72,10 @@ impl BuildParameters<'_> {
         // 如果 Minecraft 版本為 1.13 或更高版本，則獲取相關參數
         let parameters = if utils::is_mc_version("1.13", self.minecraft_version()) {
             self.build_113above()
-        } else {
+        } else if utils::is_mc_version("1.6", self.minecraft_version()) {
             self.build_112later()
+        } else {
+            self.build_legacy()
         };
 
         // 創建 Java 啟動參數結構體
@@ -82,6 +125,47 @@ impl BuildParameters<'_> {
         parameters
     }
 
+    // 生成 Minecraft 1.6 以前版本 (applet wrapper) 的啟動參數
+    fn build_legacy(&self) -> Vec<String> {
+
+        let mut parameters: Vec<String> = Vec::new();
+
+        // 添加 JVM 參數
+        parameters.extend(self.get_jvm_arguments_for_112_and_later());
+
+        // 添加通用 JVM 參數
+        parameters.extend(self.jvm_parameters());
+
+        // 添加 applet wrapper 專用 JVM 參數
+        parameters.extend(self.get_jvm_arguments_for_legacy());
+
+        // 添加主類 mainClass，使用啟動器自身的 applet wrapper 而非 Minecraft 的進入點
+        parameters.push(LEGACY_LAUNCHER_MAIN_CLASS.to_string());
+
+        // 添加 Minecraft 遊戲參數 (username, session)
+        parameters.extend(self.minecraft_arguments_for_legacy());
+
+        parameters
+    }
+
+    // 獲取 Minecraft 1.6 以前版本的 applet wrapper JVM 參數
+    fn get_jvm_arguments_for_legacy(&self) -> Vec<String> {
+
+        let mut jvm_arguments = Vec::<String>::new();
+
+        let applet_class = self.launch_profile.applet_class.as_deref().unwrap_or(LEGACY_DEFAULT_APPLET_CLASS);
+
+        jvm_arguments.push(format!("-Dminecraft.applet.TargetDirectory={}", self.launch_profile.game_directory.to_string_lossy()));
+        jvm_arguments.push(format!("-DappletClass={}", applet_class));
+
+        jvm_arguments
+    }
+
+    // 生成 Minecraft 1.6 以前版本的遊戲參數，舊版 Minecraft 進入點只接受兩個位置參數：username 與 session
+    fn minecraft_arguments_for_legacy(&self) -> Vec<String> {
+        vec![self.launch_profile.player_name.clone(), self.launch_profile.access_token.clone()]
+    }
+
     // 生成 Minecraft 1.12 及以下版本的遊戲參數
     // fn minecraft_arguments_for_112_and_later(&self) -> Vec<String> {
 
@@ -144,21 +228,23 @@ impl BuildParameters<'_> {
         let games = self.version_metadata.get_java_parameters().get_game();
         let mut game_arguments = Vec::<String>::new();
 
-        let game_instances_dir_path = global_path::get_instances_dir_path().join("mckismetlab-main-server").to_string_lossy().to_string();
+        let game_directory = self.launch_profile.game_directory.to_string_lossy().to_string();
         let assets_common_dir_path = global_path::get_common_dir_path().join("assets").to_string_lossy().to_string();
 
         // 遍歷遊戲參數
         for games in &games.arguments {
             let val = match games.key.as_str() {
-                "${auth_player_name}" => "Yu_Cheng",
+                "${auth_player_name}" => self.launch_profile.player_name.as_str(),
                 "${version_name}" => self.minecraft_version(),
-                "${game_directory}" => &game_instances_dir_path,
+                "${game_directory}" => &game_directory,
                 "${assets_root}" => &assets_common_dir_path,
                 "${assets_index_name}" => self.version_metadata.get_assets_index_id(),
-                "${auth_uuid}" => "93ea0589-ec75-4cad-8619-995164382e8d",
-                "${auth_access_token}" => "null_token",
-                "${user_type}" => "mojang",
-                "${version_type}" => "release",
+                "${auth_uuid}" => self.launch_profile.player_uuid.as_str(),
+                "${auth_access_token}" => self.launch_profile.access_token.as_str(),
+                "${auth_xuid}" => self.launch_profile.xuid.as_str(),
+                "${clientid}" => self.launch_profile.client_id.as_str(),
+                "${user_type}" => self.launch_profile.user_type.as_str(),
+                "${version_type}" => self.launch_profile.version_type.as_str(),
                 "${user_properties}" => "{}",
                 _ => continue,
             };
@@ -175,9 +261,9 @@ impl BuildParameters<'_> {
 
         let mut arguments: Vec<String> = Vec::new();
 
-        let ram_size_max = 4096;
-        let ram_size_min = 1024;
-        
+        let ram_size_max = self.launch_profile.max_heap_mb;
+        let ram_size_min = self.launch_profile.min_heap_mb;
+
         if ram_size_max != 0 {
             arguments.push(format!("-Xmx{}M", ram_size_max));
         } else {
@@ -190,6 +276,8 @@ impl BuildParameters<'_> {
             arguments.push("-Xms1024M".to_string());
         }
 
+        arguments.extend(self.launch_profile.extra_jvm_arguments.iter().cloned());
+
         arguments
     }
 
@@ -229,7 +317,14 @@ impl BuildParameters<'_> {
 
     fn assemble_library_path(&self) -> String {
 
-        let metadata_libraries = self.version_metadata.get_libraries();
+        // Run the rule engine ourselves against the raw library list rather than going through
+        // `get_libraries()`, so `self.features` actually reaches `is_rules`'s feature matching
+        // (e.g. `is_demo_user`/`has_custom_resolution`) instead of being filtered with none active.
+        let mut metadata_libraries = libraries::is_libraries(self.version_metadata.get_raw_libraries(), &self.features);
+        // Mojang's manifest only ships x86/x64 LWJGL natives; swap in the arm64 build on
+        // Raspberry Pi / Apple Silicon so the game can actually load GLFW/OpenAL.
+        libraries::apply_lwjgl_arm_overrides(&mut metadata_libraries);
+
         let mut libraries: Vec<String> = Vec::new();
 
         // Add Artifact libraries *.jar paths
@@ -241,8 +336,25 @@ impl BuildParameters<'_> {
             libraries.push(metadata_lib.path.to_string_lossy().to_string());
         }
 
-        // Add client.jar path
-        libraries.push(self.version_metadata.get_client_jar().path.to_string_lossy().to_string());
+        // Add client.jar path, patched with any jarmods in place of the plain client jar.
+        let client_jar_path = if self.jarmods.is_empty() {
+            self.version_metadata.get_client_jar().path
+        } else {
+            match jarmod::build_patched_client_jar(&self.launch_profile.game_directory, &self.version_metadata.get_client_jar().path, &self.jarmods) {
+                Ok(patched_jar_path) => patched_jar_path,
+                Err(error) => {
+                    warn!("failed to build patched client jar, launching with jarmods disabled: {}", error);
+                    self.version_metadata.get_client_jar().path
+                }
+            }
+        };
+        libraries.push(client_jar_path.to_string_lossy().to_string());
+
+        // Below 1.6 the main class is our own applet wrapper rather than anything
+        // `version_metadata` lists, so its jar has to be added to the classpath by hand.
+        if !utils::is_mc_version("1.6", self.minecraft_version()) {
+            libraries.push(self.legacy_wrapper_jar_path().to_string_lossy().to_string());
+        }
 
         // 根據操作系統類型選擇路徑分隔符
         if utils::get_os_type() == OSType::Windows {
@@ -255,4 +367,8 @@ impl BuildParameters<'_> {
     fn minecraft_version(&self) -> &str {
         self.version_metadata.get_id()
     }
+
+    fn legacy_wrapper_jar_path(&self) -> PathBuf {
+        global_path::get_common_dir_path().join("launcher").join(LEGACY_LAUNCHER_WRAPPER_JAR_NAME)
+    }
 }
\ No newline at end of file